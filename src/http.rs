@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead},
+};
+
+/// Largest body we're willing to allocate for a single request. Requests
+/// advertising a larger `Content-Length` are rejected before any allocation
+/// happens, so a malicious length can't trigger an aborting OOM.
+const MAX_BODY_LEN: usize = 10 * 1024 * 1024;
+
+/// A parsed HTTP/1.1 request.
+///
+/// Parses the request line, all headers, and (when a `Content-Length`
+/// header is present) the body out of a buffered reader.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Request {
+    /// Read and parse a single HTTP request from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request line is missing or malformed, if
+    /// `Content-Length` exceeds `MAX_BODY_LEN`, or if the underlying reader
+    /// fails.
+    pub fn parse<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let request_line = request_line.trim_end();
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing method"))?
+            .to_string();
+        let path = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing path"))?
+            .to_string();
+        let version = parts
+            .next()
+            .unwrap_or("HTTP/1.1")
+            .to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let body = match headers.get("content-length").and_then(|len| len.parse::<usize>().ok()) {
+            Some(len) if len > MAX_BODY_LEN => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Content-Length {len} exceeds the {MAX_BODY_LEN} byte limit"),
+                ));
+            }
+            Some(len) if len > 0 => {
+                let mut buf = vec![0u8; len];
+                reader.read_exact(&mut buf)?;
+                String::from_utf8_lossy(&buf).into_owned()
+            }
+            _ => String::new(),
+        };
+
+        Ok(Request { method, path, version, headers, body })
+    }
+}
+
+/// An HTTP response, ready to be serialized onto the wire.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status_code: u16,
+    pub reason: &'static str,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status_code: u16, reason: &'static str, body: impl Into<String>) -> Response {
+        Response {
+            status_code,
+            reason,
+            headers: HashMap::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<String>) -> Response {
+        Response::new(200, "OK", body)
+    }
+
+    pub fn not_found(body: impl Into<String>) -> Response {
+        Response::new(404, "NOT FOUND", body)
+    }
+}
+
+impl fmt::Display for Response {
+    /// Render the status line, headers (including `Content-Length`) and body
+    /// as the bytes to write back to the client.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let length = self.body.len();
+        let mut extra_headers = String::new();
+        for (name, value) in &self.headers {
+            // We always emit our own Content-Length below; skip a caller-set
+            // one so the response doesn't end up with two.
+            if name.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            extra_headers.push_str(&format!("{name}: {value}\r\n"));
+        }
+
+        write!(
+            f,
+            "HTTP/1.1 {} {}\r\nContent-Length: {length}\r\n{extra_headers}\r\n{}",
+            self.status_code, self.reason, self.body
+        )
+    }
+}
+
+type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
+
+/// Routes parsed requests to registered handlers by method and path.
+///
+/// Unmatched requests fall through to a default 404 handler, which can be
+/// overridden with [`Router::default_handler`].
+pub struct Router {
+    routes: HashMap<(String, String), Handler>,
+    fallback: Handler,
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router {
+            routes: HashMap::new(),
+            fallback: Box::new(|_req| Response::not_found("404 Not Found")),
+        }
+    }
+
+    /// Register a handler for `method` + `path`, e.g. `router.route("GET", "/", handler)`.
+    pub fn route(
+        &mut self,
+        method: &str,
+        path: &str,
+        handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) {
+        self.routes
+            .insert((method.to_string(), path.to_string()), Box::new(handler));
+    }
+
+    /// Override the handler used when no route matches.
+    pub fn default_handler(&mut self, handler: impl Fn(&Request) -> Response + Send + Sync + 'static) {
+        self.fallback = Box::new(handler);
+    }
+
+    /// Dispatch `request` to its registered handler, or the fallback if none matches.
+    pub fn dispatch(&self, request: &Request) -> Response {
+        let key = (request.method.clone(), request.path.clone());
+        match self.routes.get(&key) {
+            Some(handler) => handler(request),
+            None => (self.fallback)(request),
+        }
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn parse(raw: &str) -> io::Result<Request> {
+        Request::parse(&mut BufReader::new(raw.as_bytes()))
+    }
+
+    #[test]
+    fn parses_method_path_version_and_headers() {
+        let request = parse("GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/hello");
+        assert_eq!(request.version, "HTTP/1.1");
+        assert_eq!(request.headers.get("host"), Some(&"localhost".to_string()));
+        assert_eq!(request.body, "");
+    }
+
+    #[test]
+    fn missing_path_is_an_error() {
+        assert!(parse("GET\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn reads_body_via_content_length() {
+        let request = parse("POST /submit HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+        assert_eq!(request.body, "hello");
+    }
+
+    #[test]
+    fn non_numeric_content_length_is_treated_as_no_body() {
+        let request = parse("POST /submit HTTP/1.1\r\nContent-Length: nope\r\n\r\n").unwrap();
+        assert_eq!(request.body, "");
+    }
+
+    #[test]
+    fn oversized_content_length_is_rejected_before_allocating() {
+        let request = parse(&format!(
+            "POST /submit HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_LEN + 1
+        ));
+        assert!(request.is_err());
+    }
+
+    #[test]
+    fn duplicate_headers_keep_the_last_value() {
+        let request = parse("GET / HTTP/1.1\r\nX-Test: first\r\nX-Test: second\r\n\r\n").unwrap();
+        assert_eq!(request.headers.get("x-test"), Some(&"second".to_string()));
+    }
+
+    #[test]
+    fn response_display_does_not_duplicate_content_length() {
+        let mut response = Response::ok("hi");
+        response
+            .headers
+            .insert("Content-Length".to_string(), "999".to_string());
+        let rendered = response.to_string();
+        assert_eq!(rendered.matches("Content-Length").count(), 1);
+    }
+
+    #[test]
+    fn router_falls_back_to_default_handler_for_unmatched_routes() {
+        let mut router = Router::new();
+        router.route("GET", "/known", |_req| Response::ok("known"));
+
+        let request = parse("GET /unknown HTTP/1.1\r\n\r\n").unwrap();
+        let response = router.dispatch(&request);
+        assert_eq!(response.status_code, 404);
+    }
+
+    #[test]
+    fn router_dispatches_matched_routes() {
+        let mut router = Router::new();
+        router.route("GET", "/known", |_req| Response::ok("known"));
+
+        let request = parse("GET /known HTTP/1.1\r\n\r\n").unwrap();
+        let response = router.dispatch(&request);
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, "known");
+    }
+}
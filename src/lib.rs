@@ -1,18 +1,63 @@
 use std::{
-    sync::{mpsc, Arc, Mutex},
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
     thread,
 };
 
+mod http;
+pub use http::{Request, Response, Router};
+
 pub struct ThreadPool {
-    workers: Vec<Worker>,
-    sender: Option<mpsc::Sender<Message>>,
+    workers: Mutex<Vec<Worker>>,
+    shared: Arc<Shared>,
 }
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
 
-enum Message {
-    NewJob(Job),
-    Terminate,
+/// State shared between the pool and every worker: the pending job queue
+/// and the flag that tells workers to stop once it is drained.
+struct SharedState {
+    queue: VecDeque<Job>,
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<SharedState>,
+    condvar: Condvar,
+    metrics: Arc<Metrics>,
+}
+
+/// Atomic counters tracking pool saturation, surfaced via [`ThreadPool::metrics`].
+#[derive(Default)]
+pub struct Metrics {
+    accepted_jobs: AtomicUsize,
+    in_flight_jobs: AtomicUsize,
+    completed_jobs: AtomicUsize,
+    busy_workers: AtomicUsize,
+}
+
+/// A point-in-time read of a [`Metrics`]'s counters.
+#[derive(Debug, Clone, Copy)]
+pub struct MetricsSnapshot {
+    pub accepted_jobs: usize,
+    pub in_flight_jobs: usize,
+    pub completed_jobs: usize,
+    pub busy_workers: usize,
+}
+
+impl Metrics {
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            accepted_jobs: self.accepted_jobs.load(Ordering::SeqCst),
+            in_flight_jobs: self.in_flight_jobs.load(Ordering::SeqCst),
+            completed_jobs: self.completed_jobs.load(Ordering::SeqCst),
+            busy_workers: self.busy_workers.load(Ordering::SeqCst),
+        }
+    }
 }
 
 /// Custom error type for ThreadPool creation
@@ -22,6 +67,41 @@ pub enum PoolCreationError {
     ThreadCreationError(String),
 }
 
+/// The error returned by [`TaskHandle::join`] when the submitted task
+/// panicked, or when it never ran because the pool was shutting down.
+#[derive(Debug)]
+pub struct PanicError {
+    pub message: String,
+}
+
+/// Returned by [`ThreadPool::execute`] when the pool has already been told
+/// to shut down and can no longer accept jobs. [`ThreadPool::submit`] does
+/// not return it; the [`TaskHandle`] it hands back yields a [`PanicError`]
+/// from `join` instead.
+#[derive(Debug)]
+pub struct PoolShutdownError;
+
+/// A handle to a task submitted through [`ThreadPool::submit`].
+///
+/// Dropping a `TaskHandle` without calling `join` simply discards the
+/// result once the task completes.
+pub struct TaskHandle<T> {
+    receiver: mpsc::Receiver<Result<T, PanicError>>,
+}
+
+impl<T> TaskHandle<T> {
+    /// Block until the task finishes and return its result, or the panic
+    /// that replaced it. Returns a [`PanicError`] without blocking if the
+    /// pool refused the task because it was shutting down.
+    pub fn join(self) -> Result<T, PanicError> {
+        self.receiver.recv().unwrap_or_else(|_| {
+            Err(PanicError {
+                message: "worker was shut down before the task completed".to_string(),
+            })
+        })
+    }
+}
+
 impl ThreadPool {
     /// Create a new ThreadPool.
     ///
@@ -33,19 +113,22 @@ impl ThreadPool {
     pub fn new(size: usize) -> ThreadPool {
         assert!(size > 0);
 
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SharedState {
+                queue: VecDeque::new(),
+                shutdown: false,
+            }),
+            condvar: Condvar::new(),
+            metrics: Arc::new(Metrics::default()),
+        });
 
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&shared)));
         }
 
-        ThreadPool { 
-            workers, 
-            sender: Some(sender) 
-        }
+        ThreadPool { workers: Mutex::new(workers), shared }
     }
 
     /// Build a new ThreadPool with error handling.
@@ -60,13 +143,19 @@ impl ThreadPool {
             return Err(PoolCreationError::ZeroSize);
         }
 
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SharedState {
+                queue: VecDeque::new(),
+                shutdown: false,
+            }),
+            condvar: Condvar::new(),
+            metrics: Arc::new(Metrics::default()),
+        });
 
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            match Worker::build(id, Arc::clone(&receiver)) {
+            match Worker::build(id, Arc::clone(&shared)) {
                 Ok(worker) => workers.push(worker),
                 Err(err) => return Err(PoolCreationError::ThreadCreationError(
                     format!("Failed to create worker {}: {}", id, err)
@@ -74,39 +163,106 @@ impl ThreadPool {
             }
         }
 
-        Ok(ThreadPool { 
-            workers, 
-            sender: Some(sender) 
-        })
+        Ok(ThreadPool { workers: Mutex::new(workers), shared })
     }
 
-    pub fn execute<F>(&self, f: F)
+    /// Queue `f` to run on the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolShutdownError`] without running `f` if the pool has
+    /// already been told to shut down (e.g. `shutdown` was called, or the
+    /// pool is being dropped).
+    pub fn execute<F>(&self, f: F) -> Result<(), PoolShutdownError>
     where
         F: FnOnce() + Send + 'static,
     {
-        let job = Box::new(f);
-        self.sender.as_ref().unwrap().send(Message::NewJob(job)).unwrap();
+        self.respawn_dead_workers();
+
+        let job: Job = Box::new(f);
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            if state.shutdown {
+                return Err(PoolShutdownError);
+            }
+            state.queue.push_back(job);
+        }
+
+        self.shared.metrics.accepted_jobs.fetch_add(1, Ordering::SeqCst);
+        self.shared.metrics.in_flight_jobs.fetch_add(1, Ordering::SeqCst);
+        self.shared.condvar.notify_one();
+        Ok(())
     }
-}
 
-impl Drop for ThreadPool {
-    fn drop(&mut self) {
-        println!("Sending terminate message to all workers");
+    /// Shared handle to this pool's live job counters, for a `/metrics`-style
+    /// endpoint; read them with [`Metrics::snapshot`].
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.shared.metrics)
+    }
+
+    /// Like `execute`, but hands back a [`TaskHandle`] that can be `join`ed
+    /// for the closure's return value (or the panic that replaced it).
+    pub fn submit<F, T>(&self, f: F) -> TaskHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        // If the pool is shutting down, `execute` drops the job (and with
+        // it `sender`) instead of queueing it, which closes the channel and
+        // makes `TaskHandle::join` return a `PanicError` immediately rather
+        // than hang.
+        let _ = self.execute(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(f))
+                .map_err(|panic| PanicError { message: panic_message(&panic) });
+            let _ = sender.send(outcome);
+        });
+
+        TaskHandle { receiver }
+    }
 
-        // Send terminate message to all workers
-        for _ in &self.workers {
-            self.sender.as_ref().unwrap().send(Message::Terminate).unwrap();
+    /// Replace any worker whose thread has exited without being asked to,
+    /// keeping the pool at its configured size even after a job panic a
+    /// `catch_unwind` didn't manage to contain.
+    fn respawn_dead_workers(&self) {
+        if self.shared.state.lock().unwrap().shutdown {
+            return;
         }
-        
-        // Take the sender option to ensure it's dropped
-        self.sender.take();
-        
+
+        let mut workers = self.workers.lock().unwrap();
+        for worker in workers.iter_mut() {
+            let exited = matches!(&worker.thread, Some(thread) if thread.is_finished());
+            if exited {
+                println!("Worker {} exited unexpectedly; respawning", worker.id);
+                *worker = Worker::new(worker.id, Arc::clone(&self.shared));
+            }
+        }
+    }
+
+    /// Drain any queued jobs and join every worker thread.
+    ///
+    /// This is the graceful-shutdown path: workers finish the job they are
+    /// currently running, then pick up whatever is still queued before
+    /// exiting. It is safe to call this explicitly (e.g. after a SIGINT) as
+    /// well as to let it run implicitly via `Drop`.
+    pub fn shutdown(&mut self) {
+        let mut workers = self.workers.lock().unwrap();
+        if workers.iter().all(|worker| worker.thread.is_none()) {
+            // Already shut down.
+            return;
+        }
+
+        println!("Signalling shutdown to all workers");
+
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.condvar.notify_all();
+
         println!("Shutting down all workers");
-        
-        // Join all worker threads
-        for worker in &mut self.workers {
+
+        for worker in workers.iter_mut() {
             println!("Shutting down worker {}", worker.id);
-            
+
             if let Some(thread) = worker.thread.take() {
                 thread.join().unwrap();
             }
@@ -114,52 +270,80 @@ impl Drop for ThreadPool {
     }
 }
 
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Best-effort extraction of a human-readable message from a caught panic.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
 struct Worker {
     id: usize,
     thread: Option<thread::JoinHandle<()>>,
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
-        let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv().unwrap();
-            
-            match message {
-                Message::NewJob(job) => {
-                    println!("Worker {id} got a job; executing.");
-                    job();
-                }
-                Message::Terminate => {
-                    println!("Worker {id} was told to terminate.");
-                    break;
-                }
-            }
-        });
+    fn new(id: usize, shared: Arc<Shared>) -> Worker {
+        let thread = thread::spawn(move || Worker::run(id, &shared));
 
         Worker { id, thread: Some(thread) }
     }
 
-    fn build(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Result<Worker, std::io::Error> {
-        let thread = match std::thread::Builder::new()
+    fn build(id: usize, shared: Arc<Shared>) -> Result<Worker, std::io::Error> {
+        let thread = std::thread::Builder::new()
             .name(format!("worker-{}", id))
-            .spawn(move || loop {
-                let message = receiver.lock().unwrap().recv().unwrap();
-                
-                match message {
-                    Message::NewJob(job) => {
-                        println!("Worker {id} got a job; executing.");
-                        job();
-                    }
-                    Message::Terminate => {
-                        println!("Worker {id} was told to terminate.");
-                        break;
-                    }
+            .spawn(move || Worker::run(id, &shared))?;
+
+        Ok(Worker { id, thread: Some(thread) })
+    }
+
+    /// Pop a job and run it with the lock released, or wait on the condvar
+    /// until one arrives; exit once the queue is empty and `shutdown` is set.
+    fn run(id: usize, shared: &Shared) {
+        loop {
+            let mut state = shared.state.lock().unwrap();
+
+            let job = loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break Some(job);
+                }
+                if state.shutdown {
+                    break None;
                 }
-            }) {
-                Ok(thread) => thread,
-                Err(e) => return Err(e),
+                state = shared.condvar.wait(state).unwrap();
             };
 
-        Ok(Worker { id, thread: Some(thread) })
+            drop(state);
+
+            match job {
+                Some(job) => {
+                    println!("Worker {id} got a job; executing.");
+
+                    shared.metrics.busy_workers.fetch_add(1, Ordering::SeqCst);
+                    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(job)) {
+                        let message = panic_message(&panic);
+                        eprintln!("Worker {id} job panicked: {message}");
+                    }
+                    shared.metrics.busy_workers.fetch_sub(1, Ordering::SeqCst);
+
+                    shared.metrics.in_flight_jobs.fetch_sub(1, Ordering::SeqCst);
+                    shared.metrics.completed_jobs.fetch_add(1, Ordering::SeqCst);
+                }
+                None => {
+                    println!("Worker {id} was told to terminate.");
+                    break;
+                }
+            }
+        }
     }
 }
@@ -2,16 +2,73 @@ use std::{
     fs,
     io::{prelude::*, BufReader},
     net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
     time::Duration,
 };
-use hello::ThreadPool;
+use hello::{Metrics, Request, Response, Router, ThreadPool};
+
+/// Cleared by `handle_sigint` when the operator asks the server to stop.
+static RUNNING: AtomicBool = AtomicBool::new(true);
+
+const SIGINT: i32 = 2;
+
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+}
+
+extern "C" fn handle_sigint(_signum: i32) {
+    RUNNING.store(false, Ordering::SeqCst);
+}
+
+fn build_router(metrics: Arc<Metrics>) -> Router {
+    let mut router = Router::new();
+
+    router.route("GET", "/", |_req| {
+        let contents = fs::read_to_string("hello.html").unwrap();
+        Response::ok(contents)
+    });
+
+    router.route("GET", "/sleep", |_req| {
+        thread::sleep(Duration::from_secs(10));
+        let contents = fs::read_to_string("hello.html").unwrap();
+        Response::ok(contents)
+    });
+
+    router.route("GET", "/metrics", move |_req| {
+        let snapshot = metrics.snapshot();
+        let body = format!(
+            "{{\"accepted_jobs\":{},\"in_flight_jobs\":{},\"completed_jobs\":{},\"busy_workers\":{}}}",
+            snapshot.accepted_jobs, snapshot.in_flight_jobs, snapshot.completed_jobs, snapshot.busy_workers
+        );
+        let mut response = Response::ok(body);
+        response
+            .headers
+            .insert("Content-Type".to_string(), "application/json".to_string());
+        response
+    });
+
+    router.default_handler(|_req| {
+        let contents = fs::read_to_string("404.html").unwrap();
+        Response::not_found(contents)
+    });
+
+    router
+}
 
 fn main() {
     let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
-    
+    listener.set_nonblocking(true).unwrap();
+
+    unsafe {
+        signal(SIGINT, handle_sigint as *const () as usize);
+    }
+
     // Using build instead of new to demonstrate error handling
-    let pool = match ThreadPool::build(4) {
+    let mut pool = match ThreadPool::build(4) {
         Ok(pool) => pool,
         Err(e) => {
             eprintln!("Failed to create thread pool: {:?}", e);
@@ -19,35 +76,48 @@ fn main() {
         }
     };
 
-    // Handle only a limited number of requests to demonstrate proper shutdown
-    for stream in listener.incoming().take(10) {
-        let stream = stream.unwrap();
-        
-        pool.execute(|| {
-            handle_connection(stream);
-        });
+    let router = Arc::new(build_router(pool.metrics()));
+
+    // Poll for connections so we can check the running flag between accepts
+    // instead of blocking forever in `accept`.
+    while RUNNING.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _addr)) => {
+                stream.set_nonblocking(false).unwrap();
+                let router = Arc::clone(&router);
+                if pool
+                    .execute(move || {
+                        handle_connection(stream, &router);
+                    })
+                    .is_err()
+                {
+                    eprintln!("Dropping connection: thread pool is shutting down");
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => {
+                eprintln!("Failed to accept connection: {e}");
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
     }
-    
-    println!("Shutting down server after processing 10 requests");
-    // The ThreadPool will be automatically dropped here, triggering the Drop implementation
+
+    println!("Received shutdown signal, draining in-flight work");
+    pool.shutdown();
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let buf_reader = BufReader::new(&mut stream);
-    let request_line = buf_reader.lines().next().unwrap().unwrap();
-    
-    let (status_line, filename) = match &request_line[..] {
-        "GET / HTTP/1.1" => ("HTTP/1.1 200 OK", "hello.html"),
-        "GET /sleep HTTP/1.1" => {
-            thread::sleep(Duration::from_secs(10));
-            ("HTTP/1.1 200 OK", "hello.html")
+fn handle_connection(mut stream: TcpStream, router: &Router) {
+    let mut buf_reader = BufReader::new(&mut stream);
+    let request = match Request::parse(&mut buf_reader) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("Failed to parse request: {e}");
+            return;
         }
-        _ => ("HTTP/1.1 404 NOT FOUND", "404.html"),
     };
 
-    let contents = fs::read_to_string(filename).unwrap();
-    let length = contents.len();
-
-    let response = format!("{status_line}\r\nContent-Length: {length}\r\n\r\n{contents}");
-    stream.write_all(response.as_bytes()).unwrap();
+    let response = router.dispatch(&request);
+    stream.write_all(response.to_string().as_bytes()).unwrap();
 }